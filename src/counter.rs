@@ -1,40 +1,208 @@
 use solana_metrics::{influxdb, submit};
 use solana_sdk::timing;
 use std::env;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicIsize, AtomicUsize, Ordering};
+use std::sync::{Arc, Once, RwLock, ONCE_INIT};
 
 const DEFAULT_LOG_RATE: usize = 1000;
+/// milliseconds between time-based flushes when `SOLANA_METRICS_FLUSH_INTERVAL` is unset
+const DEFAULT_FLUSH_INTERVAL_MS: usize = 10_000;
+
+/// a value reported to a `MetricSink`, independent of any particular backend's
+/// native value type
+#[derive(Clone, Debug, PartialEq)]
+pub enum MetricValue {
+    Integer(i64),
+    Float(f64),
+    String(String),
+}
+
+impl From<MetricValue> for influxdb::Value {
+    fn from(value: MetricValue) -> Self {
+        match value {
+            MetricValue::Integer(i) => influxdb::Value::Integer(i),
+            MetricValue::Float(f) => influxdb::Value::Float(f),
+            MetricValue::String(s) => influxdb::Value::String(s),
+        }
+    }
+}
+
+/// a drain for counter data; the default reports to influxdb, but a deployment
+/// can point counters at statsd, a Prometheus exposition buffer, a local file,
+/// or (in tests) a capturing sink, without touching any call site
+pub trait MetricSink: Send + Sync {
+    fn report(&self, name: &str, fields: &[(&str, MetricValue)]);
+}
+
+/// the stock sink: formats fields onto an `influxdb::Point` and submits it
+pub struct InfluxDbSink;
+
+impl MetricSink for InfluxDbSink {
+    fn report(&self, name: &str, fields: &[(&str, MetricValue)]) {
+        let mut point = influxdb::Point::new(name);
+        for (field, value) in fields {
+            point
+                .fields
+                .insert(field.to_string(), value.clone().into());
+        }
+        submit(point);
+    }
+}
+
+fn default_sink_lock() -> &'static RwLock<Arc<dyn MetricSink>> {
+    static mut SINK: Option<RwLock<Arc<dyn MetricSink>>> = None;
+    static INIT_HOOK: Once = ONCE_INIT;
+    unsafe {
+        INIT_HOOK.call_once(|| {
+            SINK = Some(RwLock::new(Arc::new(InfluxDbSink) as Arc<dyn MetricSink>));
+        });
+        SINK.as_ref().unwrap()
+    }
+}
+
+/// route all counters to `sink` instead of the default influxdb backend
+pub fn set_default_sink(sink: Arc<dyn MetricSink>) {
+    *default_sink_lock().write().unwrap() = sink;
+}
+
+/// the kind of instrument a `Counter` is acting as; shares the same flush/log
+/// plumbing, but tags the emitted point so dashboards know how to read it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// monotonically increasing total, e.g. requests served
+    Counter,
+    /// point-in-time value that is replaced rather than accumulated, e.g. queue depth
+    Gauge,
+    /// elapsed-duration samples, reported as min/max/mean latency per window
+    Timer,
+    /// signed value that can move up or down, e.g. open connection count
+    Level,
+}
+
+impl Kind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Kind::Counter => "counter",
+            Kind::Gauge => "gauge",
+            Kind::Timer => "timer",
+            Kind::Level => "level",
+        }
+    }
+}
 
 pub struct Counter {
     pub name: &'static str,
+    pub kind: Kind,
+    /// namespace prepended to `name` when building the reported measurement, e.g.
+    /// "banking." groups counters under `counter-banking-<name>"; "" means ungrouped
+    pub prefix: &'static str,
     /// total accumulated value
     pub counts: AtomicUsize,
+    /// current value for `Kind::Level`, tracked separately since it can go negative
+    pub level: AtomicIsize,
     pub times: AtomicUsize,
     /// last accumulated value logged
     pub lastlog: AtomicUsize,
     pub lograte: AtomicUsize,
-    pub point: Option<influxdb::Point>,
+    /// number of samples (`inc`/`set`/`time`/`adjust` calls) since the window opened
+    pub window_count: AtomicUsize,
+    /// sum of sample values since the window opened
+    pub window_sum: AtomicUsize,
+    /// smallest single sample value seen this window (usize::MAX == no samples yet)
+    pub window_min: AtomicUsize,
+    /// largest single sample value seen this window
+    pub window_max: AtomicUsize,
+    /// `timing::timestamp()` captured when the current window opened; doubles as the
+    /// last-flush timestamp for `SOLANA_METRICS_FLUSH_INTERVAL`-based flushing
+    pub window_start: AtomicUsize,
+    /// milliseconds between time-based flushes; 0 means "resolve from env on first use"
+    pub flush_interval: AtomicUsize,
 }
 
-macro_rules! create_counter {
-    ($name:expr, $lograte:expr) => {
+macro_rules! create_metric {
+    ($name:expr, $lograte:expr, $kind:expr) => {
+        create_metric!($name, $lograte, $kind, "")
+    };
+    ($name:expr, $lograte:expr, $kind:expr, $prefix:expr) => {
         Counter {
             name: $name,
+            kind: $kind,
+            prefix: $prefix,
             counts: std::sync::atomic::AtomicUsize::new(0),
+            level: std::sync::atomic::AtomicIsize::new(0),
             times: std::sync::atomic::AtomicUsize::new(0),
             lastlog: std::sync::atomic::AtomicUsize::new(0),
             lograte: std::sync::atomic::AtomicUsize::new($lograte),
-            point: None,
+            window_count: std::sync::atomic::AtomicUsize::new(0),
+            window_sum: std::sync::atomic::AtomicUsize::new(0),
+            window_min: std::sync::atomic::AtomicUsize::new(std::usize::MAX),
+            window_max: std::sync::atomic::AtomicUsize::new(0),
+            window_start: std::sync::atomic::AtomicUsize::new(0),
+            flush_interval: std::sync::atomic::AtomicUsize::new(0),
         }
     };
 }
 
+macro_rules! create_counter {
+    ($name:expr, $lograte:expr) => {
+        create_metric!($name, $lograte, $crate::counter::Kind::Counter)
+    };
+    ($name:expr, $lograte:expr, $prefix:expr) => {
+        create_metric!($name, $lograte, $crate::counter::Kind::Counter, $prefix)
+    };
+}
+
+macro_rules! create_gauge {
+    ($name:expr, $lograte:expr) => {
+        create_metric!($name, $lograte, $crate::counter::Kind::Gauge)
+    };
+    ($name:expr, $lograte:expr, $prefix:expr) => {
+        create_metric!($name, $lograte, $crate::counter::Kind::Gauge, $prefix)
+    };
+}
+
+macro_rules! create_timer {
+    ($name:expr, $lograte:expr) => {
+        create_metric!($name, $lograte, $crate::counter::Kind::Timer)
+    };
+    ($name:expr, $lograte:expr, $prefix:expr) => {
+        create_metric!($name, $lograte, $crate::counter::Kind::Timer, $prefix)
+    };
+}
+
+macro_rules! create_level {
+    ($name:expr, $lograte:expr) => {
+        create_metric!($name, $lograte, $crate::counter::Kind::Level)
+    };
+    ($name:expr, $lograte:expr, $prefix:expr) => {
+        create_metric!($name, $lograte, $crate::counter::Kind::Level, $prefix)
+    };
+}
+
 macro_rules! inc_counter {
     ($name:expr, $level:expr, $count:expr) => {
         unsafe { $name.inc($level, $count) };
     };
 }
 
+macro_rules! set_gauge {
+    ($name:expr, $level:expr, $value:expr) => {
+        unsafe { $name.set($level, $value) };
+    };
+}
+
+macro_rules! time_timer {
+    ($name:expr, $level:expr, $elapsed:expr) => {
+        unsafe { $name.time($level, $elapsed) };
+    };
+}
+
+macro_rules! adjust_level {
+    ($name:expr, $level:expr, $delta:expr) => {
+        unsafe { $name.adjust($level, $delta) };
+    };
+}
+
 macro_rules! inc_new_counter_info {
     ($name:expr, $count:expr) => {{
         inc_new_counter!($name, $count, Level::Info, 0);
@@ -68,13 +236,161 @@ impl Counter {
             v
         }
     }
+    fn default_flush_interval() -> usize {
+        let v = env::var("SOLANA_METRICS_FLUSH_INTERVAL")
+            .map(|x| x.parse().unwrap_or(DEFAULT_FLUSH_INTERVAL_MS))
+            .unwrap_or(DEFAULT_FLUSH_INTERVAL_MS);
+        if v == 0 {
+            DEFAULT_FLUSH_INTERVAL_MS
+        } else {
+            v
+        }
+    }
     pub fn init(&mut self) {
-        self.point = Some(
-            influxdb::Point::new(&format!("counter-{}", self.name))
-                .add_field("count", influxdb::Value::Integer(0))
-                .to_owned(),
-        );
+        self.window_start
+            .store(timing::timestamp() as usize, Ordering::Relaxed);
     }
+
+    /// the measurement name reported to the sink, e.g. "counter-banking-tx_received"
+    /// for a counter named "tx_received" under the "banking." prefix
+    fn point_name(&self) -> String {
+        if self.prefix.is_empty() {
+            format!("counter-{}", self.name)
+        } else {
+            format!("counter-{}-{}", self.prefix.trim_end_matches('.'), self.name)
+        }
+    }
+
+    /// atomically update `window_min` to the smaller of its current value and `events`
+    fn update_min(&self, events: usize) {
+        let mut min = self.window_min.load(Ordering::Relaxed);
+        while events < min {
+            let prev = self
+                .window_min
+                .compare_and_swap(min, events, Ordering::Relaxed);
+            if prev == min {
+                break;
+            }
+            min = prev;
+        }
+    }
+
+    /// atomically update `window_max` to the larger of its current value and `events`
+    fn update_max(&self, events: usize) {
+        let mut max = self.window_max.load(Ordering::Relaxed);
+        while events > max {
+            let prev = self
+                .window_max
+                .compare_and_swap(max, events, Ordering::Relaxed);
+            if prev == max {
+                break;
+            }
+            max = prev;
+        }
+    }
+
+    /// record a sample into the current window's count/sum/min/max accumulators
+    fn record_sample(&self, value: usize) {
+        self.window_count.fetch_add(1, Ordering::Relaxed);
+        self.window_sum.fetch_add(value, Ordering::Relaxed);
+        self.update_min(value);
+        self.update_max(value);
+    }
+
+    /// seed `window_start` on first use, so a `Counter` that never had `init()`
+    /// called on it doesn't report a window spanning back to the Unix epoch
+    fn ensure_window_started(&self) {
+        if self.window_start.load(Ordering::Relaxed) == 0 {
+            let now = timing::timestamp() as usize;
+            // losing this race just means another caller already seeded it
+            self.window_start
+                .compare_and_swap(0, now, Ordering::Relaxed);
+        }
+    }
+
+    /// claim and emit the current window every `lograte` calls, i.e. on the same
+    /// cadence as the periodic log line below, or if the `SOLANA_METRICS_FLUSH_INTERVAL`
+    /// wall-clock window has elapsed
+    fn maybe_flush(&mut self, times: usize, lograte: usize) {
+        self.ensure_window_started();
+        let due = lograte > 0 && times > 0 && times % lograte == 0;
+        let count_elapsed = due && {
+            let lastlog = self.lastlog.load(Ordering::Relaxed);
+            self.lastlog
+                .compare_and_swap(lastlog, times, Ordering::Relaxed)
+                == lastlog
+        };
+        // read before `claim_time_flush`, which moves `window_start` to "now" when
+        // it claims a flush
+        let window_start = self.window_start.load(Ordering::Relaxed);
+        // always evaluate both triggers; the time-based one must run every call so
+        // it still resolves/advances the window even when count-based flushing wins
+        let time_elapsed = self.claim_time_flush();
+
+        if count_elapsed || time_elapsed {
+            self.flush_window(window_start);
+        }
+    }
+
+    /// atomically claim a time-based flush if at least `flush_interval` milliseconds
+    /// have passed since the window opened; safe to call even when another thread
+    /// races it, since only the CAS winner proceeds
+    fn claim_time_flush(&mut self) -> bool {
+        let mut interval = self.flush_interval.load(Ordering::Relaxed);
+        if interval == 0 {
+            interval = Counter::default_flush_interval();
+            self.flush_interval.store(interval, Ordering::Relaxed);
+        }
+        let window_start = self.window_start.load(Ordering::Relaxed);
+        let now = timing::timestamp() as usize;
+        if now.saturating_sub(window_start) < interval {
+            return false;
+        }
+        self.window_start
+            .compare_and_swap(window_start, now, Ordering::Relaxed)
+            == window_start
+    }
+
+    fn flush_window(&mut self, window_start: usize) {
+        let window_count = self.window_count.swap(0, Ordering::Relaxed);
+        let sum = self.window_sum.swap(0, Ordering::Relaxed);
+        let min = self.window_min.swap(std::usize::MAX, Ordering::Relaxed);
+        let max = self.window_max.swap(0, Ordering::Relaxed);
+        self.window_start
+            .store(timing::timestamp() as usize, Ordering::Relaxed);
+
+        let min = if min == std::usize::MAX { 0 } else { min };
+        let mean = if window_count > 0 {
+            sum as f64 / window_count as f64
+        } else {
+            0.0
+        };
+        let elapsed_secs =
+            (timing::timestamp() as usize).saturating_sub(window_start) as f64 / 1000.0;
+        let rate = if elapsed_secs > 0.0 {
+            sum as f64 / elapsed_secs
+        } else {
+            0.0
+        };
+
+        let mut fields = vec![
+            ("kind", MetricValue::String(self.kind.as_str().to_string())),
+            ("count", MetricValue::Integer(window_count as i64)),
+            ("min", MetricValue::Integer(min as i64)),
+            ("max", MetricValue::Integer(max as i64)),
+            ("mean", MetricValue::Float(mean)),
+        ];
+        // a Gauge's samples are successive point-in-time readings, not additive
+        // events, so summing or rating them (e.g. summing queue-depth readings)
+        // isn't a meaningful quantity
+        if self.kind != Kind::Gauge {
+            fields.push(("sum", MetricValue::Integer(sum as i64)));
+            fields.push(("rate", MetricValue::Float(rate)));
+        }
+        let sink = default_sink_lock().read().unwrap().clone();
+        sink.report(&self.point_name(), &fields);
+    }
+
     pub fn inc(&mut self, level: log::Level, events: usize) {
         let counts = self.counts.fetch_add(events, Ordering::Relaxed);
         let times = self.times.fetch_add(1, Ordering::Relaxed);
@@ -85,7 +401,8 @@ impl Counter {
         }
         if times % lograte == 0 && times > 0 && log_enabled!(level) {
             info!(
-                "COUNTER:{{\"name\": \"{}\", \"counts\": {}, \"samples\": {},  \"now\": {}, \"events\": {}}}",
+                "{}:{{\"name\": \"{}\", \"counts\": {}, \"samples\": {},  \"now\": {}, \"events\": {}}}",
+                self.kind.as_str().to_uppercase(),
                 self.name,
                 counts + events,
                 times,
@@ -93,32 +410,76 @@ impl Counter {
                 events,
             );
         }
-        let lastlog = self.lastlog.load(Ordering::Relaxed);
-        let prev = self
-            .lastlog
-            .compare_and_swap(lastlog, counts, Ordering::Relaxed);
-        if prev == lastlog {
-            if let Some(ref mut point) = self.point {
-                point
-                    .fields
-                    .entry("count".to_string())
-                    .and_modify(|v| *v = influxdb::Value::Integer(counts as i64 - lastlog as i64))
-                    .or_insert(influxdb::Value::Integer(0));
-            }
-            if let Some(ref mut point) = self.point {
-                submit(point.to_owned());
-            }
+        self.record_sample(events);
+        self.maybe_flush(times, lograte);
+    }
+
+    /// `Kind::Gauge`: set the current value, replacing whatever was there before
+    pub fn set(&mut self, level: log::Level, value: usize) {
+        self.counts.swap(value, Ordering::Relaxed);
+        let times = self.times.fetch_add(1, Ordering::Relaxed);
+        let mut lograte = self.lograte.load(Ordering::Relaxed);
+        if lograte == 0 {
+            lograte = Counter::default_log_rate();
+            self.lograte.store(lograte, Ordering::Relaxed);
+        }
+        if times % lograte == 0 && times > 0 && log_enabled!(level) {
+            info!(
+                "GAUGE:{{\"name\": \"{}\", \"value\": {}, \"samples\": {}, \"now\": {}}}",
+                self.name,
+                value,
+                times,
+                timing::timestamp(),
+            );
         }
+        self.record_sample(value);
+        self.maybe_flush(times, lograte);
+    }
+
+    /// `Kind::Timer`: record an elapsed duration; reported as min/max/mean per window
+    pub fn time(&mut self, level: log::Level, elapsed: usize) {
+        self.inc(level, elapsed)
+    }
+
+    /// `Kind::Level`: move the current value up or down by `delta`
+    pub fn adjust(&mut self, level: log::Level, delta: isize) {
+        let prev = self.level.fetch_add(delta, Ordering::Relaxed);
+        let times = self.times.fetch_add(1, Ordering::Relaxed);
+        let mut lograte = self.lograte.load(Ordering::Relaxed);
+        if lograte == 0 {
+            lograte = Counter::default_log_rate();
+            self.lograte.store(lograte, Ordering::Relaxed);
+        }
+        if times % lograte == 0 && times > 0 && log_enabled!(level) {
+            info!(
+                "LEVEL:{{\"name\": \"{}\", \"level\": {}, \"samples\": {}, \"now\": {}, \"delta\": {}}}",
+                self.name,
+                prev + delta,
+                times,
+                timing::timestamp(),
+                delta,
+            );
+        }
+        self.record_sample(delta.unsigned_abs());
+        self.maybe_flush(times, lograte);
     }
 }
 #[cfg(test)]
 mod tests {
-    use crate::counter::{Counter, DEFAULT_LOG_RATE};
+    use crate::counter::{
+        set_default_sink, Counter, MetricSink, MetricValue, DEFAULT_FLUSH_INTERVAL_MS,
+        DEFAULT_LOG_RATE,
+    };
     use log::Level;
     use std::env;
     use std::sync::atomic::Ordering;
-    use std::sync::{Once, RwLock, ONCE_INIT};
+    use std::sync::{Arc, Mutex, Once, RwLock, ONCE_INIT};
 
+    // `get_env_lock` and `get_sink_lock` below are shared test infrastructure;
+    // a commit adding an unrelated feature should not touch them incidentally
+    // (a prior commit in this module's history did, dropping `get_sink_lock`
+    // and quietly repointing sink tests at `get_env_lock` as a side effect of
+    // adding the namespace-prefix feature, which took a follow-up fix to undo)
     fn get_env_lock() -> &'static RwLock<()> {
         static mut ENV_LOCK: Option<RwLock<()>> = None;
         static INIT_HOOK: Once = ONCE_INIT;
@@ -131,9 +492,45 @@ mod tests {
         }
     }
 
+    // guards the process-global default sink, kept separate from `get_env_lock`
+    // since it's unrelated global state. every test whose counter might flush
+    // (i.e. report through whatever the current default sink is) must take at
+    // least a `.read()` here, mirroring `get_env_lock`'s convention; tests that
+    // swap the sink itself take a `.write()` so no other counter can report
+    // into their `CapturingSink` while it's installed
+    fn get_sink_lock() -> &'static RwLock<()> {
+        static mut SINK_LOCK: Option<RwLock<()>> = None;
+        static INIT_HOOK: Once = ONCE_INIT;
+
+        unsafe {
+            INIT_HOOK.call_once(|| {
+                SINK_LOCK = Some(RwLock::new(()));
+            });
+            &SINK_LOCK.as_ref().unwrap()
+        }
+    }
+
+    #[derive(Default)]
+    struct CapturingSink {
+        reports: Mutex<Vec<(String, Vec<(String, MetricValue)>)>>,
+    }
+
+    impl MetricSink for CapturingSink {
+        fn report(&self, name: &str, fields: &[(&str, MetricValue)]) {
+            self.reports.lock().unwrap().push((
+                name.to_string(),
+                fields
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.clone()))
+                    .collect(),
+            ));
+        }
+    }
+
     #[test]
     fn test_counter() {
         let _readlock = get_env_lock().read();
+        let _sinklock = get_sink_lock().read();
         static mut COUNTER: Counter = create_counter!("test", 1000);
         let count = 1;
         inc_counter!(COUNTER, Level::Info, count);
@@ -148,16 +545,192 @@ mod tests {
             inc_counter!(COUNTER, Level::Info, 2);
         }
         unsafe {
-            assert_eq!(COUNTER.lastlog.load(Ordering::Relaxed), 397);
+            // `lastlog` only moves when `times` crosses a `lograte` boundary; with
+            // lograte == 1000 and 200 total calls, that never happens
+            assert_eq!(COUNTER.lastlog.load(Ordering::Relaxed), 0);
         }
         inc_counter!(COUNTER, Level::Info, 2);
         unsafe {
-            assert_eq!(COUNTER.lastlog.load(Ordering::Relaxed), 399);
+            assert_eq!(COUNTER.lastlog.load(Ordering::Relaxed), 0);
+        }
+    }
+    #[test]
+    fn test_counter_window_stats() {
+        let _readlock = get_env_lock().read();
+        let _sinklock = get_sink_lock().read();
+        static mut COUNTER: Counter = create_counter!("test_window_stats", 3);
+        unsafe {
+            COUNTER.init();
+        }
+        // `times` (the call count fetched *before* incrementing) is 0, 1, 2 for
+        // these three calls, so none of them land on a `times % lograte == 0`
+        // boundary yet and all three samples accumulate into the same window
+        inc_counter!(COUNTER, Level::Info, 5);
+        inc_counter!(COUNTER, Level::Info, 1);
+        inc_counter!(COUNTER, Level::Info, 2);
+        unsafe {
+            assert_eq!(COUNTER.window_count.load(Ordering::Relaxed), 3);
+            assert_eq!(COUNTER.window_sum.load(Ordering::Relaxed), 8);
+            assert_eq!(COUNTER.window_min.load(Ordering::Relaxed), 1);
+            assert_eq!(COUNTER.window_max.load(Ordering::Relaxed), 5);
+        }
+        // the fourth call observes times == 3 == lograte, crossing the flush
+        // boundary and resetting the window accumulators
+        inc_counter!(COUNTER, Level::Info, 3);
+        unsafe {
+            assert_eq!(COUNTER.window_count.load(Ordering::Relaxed), 0);
+            assert_eq!(COUNTER.window_sum.load(Ordering::Relaxed), 0);
+            assert_eq!(COUNTER.window_min.load(Ordering::Relaxed), std::usize::MAX);
+            assert_eq!(COUNTER.window_max.load(Ordering::Relaxed), 0);
+        }
+    }
+    #[test]
+    fn test_custom_sink() {
+        let _writelock = get_sink_lock().write();
+        let sink = Arc::new(CapturingSink::default());
+        set_default_sink(sink.clone());
+
+        // lograte of 1 so the second call (times == 1) lands on the flush
+        // cadence; the first call never flushes (times == 0)
+        static mut COUNTER: Counter = create_counter!("test_custom_sink", 1);
+        unsafe {
+            COUNTER.init();
+        }
+        inc_counter!(COUNTER, Level::Info, 7);
+        inc_counter!(COUNTER, Level::Info, 7);
+
+        let reports = sink.reports.lock().unwrap();
+        assert_eq!(reports.len(), 1);
+        let (name, fields) = &reports[0];
+        assert_eq!(name, "counter-test_custom_sink");
+        assert!(fields
+            .iter()
+            .any(|(k, v)| k == "sum" && *v == MetricValue::Integer(14)));
+
+        set_default_sink(Arc::new(crate::counter::InfluxDbSink));
+    }
+    #[test]
+    fn test_counter_prefix() {
+        let _writelock = get_sink_lock().write();
+        let sink = Arc::new(CapturingSink::default());
+        set_default_sink(sink.clone());
+
+        // lograte of 1 so the second call (times == 1) lands on the flush
+        // cadence; the first call never flushes (times == 0)
+        static mut COUNTER: Counter = create_counter!("tx_received", 1, "banking.");
+        unsafe {
+            COUNTER.init();
+        }
+        inc_counter!(COUNTER, Level::Info, 1);
+        inc_counter!(COUNTER, Level::Info, 1);
+
+        let reports = sink.reports.lock().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].0, "counter-banking-tx_received");
+
+        set_default_sink(Arc::new(crate::counter::InfluxDbSink));
+    }
+    #[test]
+    fn test_gauge_flush_omits_sum_and_rate() {
+        let _writelock = get_sink_lock().write();
+        let sink = Arc::new(CapturingSink::default());
+        set_default_sink(sink.clone());
+
+        // lograte of 1 so the second call (times == 1) lands on the flush
+        // cadence; the first call never flushes (times == 0)
+        static mut GAUGE: Counter = create_gauge!("test_gauge_flush", 1);
+        unsafe {
+            GAUGE.init();
+        }
+        set_gauge!(GAUGE, Level::Info, 5);
+        set_gauge!(GAUGE, Level::Info, 2);
+
+        let reports = sink.reports.lock().unwrap();
+        assert_eq!(reports.len(), 1);
+        let (_, fields) = &reports[0];
+        // summing or rating successive point-in-time gauge readings isn't a
+        // meaningful quantity, unlike for Counter/Timer/Level
+        assert!(!fields.iter().any(|(k, _)| k == "sum"));
+        assert!(!fields.iter().any(|(k, _)| k == "rate"));
+        assert!(fields.iter().any(|(k, _)| k == "mean"));
+
+        set_default_sink(Arc::new(crate::counter::InfluxDbSink));
+    }
+    #[test]
+    fn test_window_start_seeded_without_init() {
+        let _readlock = get_env_lock().read();
+        let _sinklock = get_sink_lock().read();
+        // a single call is enough to seed `window_start`, without ever calling
+        // `init()` (`test_gauge`/`test_timer`/`test_level` don't call it either)
+        static mut COUNTER: Counter = create_counter!("test_window_start_seeded", 1);
+        unsafe {
+            assert_eq!(COUNTER.window_start.load(Ordering::Relaxed), 0);
+        }
+        inc_counter!(COUNTER, Level::Info, 1);
+        unsafe {
+            // seeded to "now" on first use rather than left at 0 (the Unix epoch),
+            // which would otherwise make the next flush report a bogus near-zero rate
+            // over a window spanning back to the epoch
+            assert_ne!(COUNTER.window_start.load(Ordering::Relaxed), 0);
+        }
+    }
+    #[test]
+    fn test_gauge() {
+        let _readlock = get_env_lock().read();
+        let _sinklock = get_sink_lock().read();
+        static mut GAUGE: Counter = create_gauge!("test_gauge", 1000);
+        set_gauge!(GAUGE, Level::Info, 5);
+        unsafe {
+            assert_eq!(GAUGE.kind, super::Kind::Gauge);
+            assert_eq!(GAUGE.counts.load(Ordering::Relaxed), 5);
+        }
+        set_gauge!(GAUGE, Level::Info, 2);
+        unsafe {
+            // a gauge replaces rather than accumulates
+            assert_eq!(GAUGE.counts.load(Ordering::Relaxed), 2);
+        }
+    }
+    #[test]
+    fn test_timer() {
+        let _readlock = get_env_lock().read();
+        let _sinklock = get_sink_lock().read();
+        static mut TIMER: Counter = create_timer!("test_timer", 1000);
+        time_timer!(TIMER, Level::Info, 42);
+        unsafe {
+            assert_eq!(TIMER.kind, super::Kind::Timer);
+            assert_eq!(TIMER.counts.load(Ordering::Relaxed), 42);
+        }
+    }
+    #[test]
+    fn test_level() {
+        let _readlock = get_env_lock().read();
+        let _sinklock = get_sink_lock().read();
+        static mut LEVEL: Counter = create_level!("test_level", 1000);
+        adjust_level!(LEVEL, Level::Info, 5);
+        unsafe {
+            assert_eq!(LEVEL.kind, super::Kind::Level);
+            assert_eq!(LEVEL.level.load(Ordering::Relaxed), 5);
+        }
+        adjust_level!(LEVEL, Level::Info, -8);
+        unsafe {
+            assert_eq!(LEVEL.level.load(Ordering::Relaxed), -3);
+        }
+    }
+    #[test]
+    fn test_level_adjust_isize_min_does_not_panic() {
+        let _readlock = get_env_lock().read();
+        let _sinklock = get_sink_lock().read();
+        static mut LEVEL: Counter = create_level!("test_level_isize_min", 1000);
+        // delta.abs() panics on isize::MIN in debug builds; unsigned_abs() doesn't
+        adjust_level!(LEVEL, Level::Info, std::isize::MIN);
+        unsafe {
+            assert_eq!(LEVEL.level.load(Ordering::Relaxed), std::isize::MIN);
         }
     }
     #[test]
     fn test_inc_new_counter() {
         let _readlock = get_env_lock().read();
+        let _sinklock = get_sink_lock().read();
         //make sure that macros are syntactically correct
         //the variable is internal to the macro scope so there is no way to introspect it
         inc_new_counter_info!("counter-1", 1);
@@ -166,6 +739,7 @@ mod tests {
     #[test]
     fn test_lograte() {
         let _readlock = get_env_lock().read();
+        let _sinklock = get_sink_lock().read();
         assert_eq!(
             Counter::default_log_rate(),
             DEFAULT_LOG_RATE,
@@ -184,6 +758,7 @@ mod tests {
     fn test_lograte_env() {
         assert_ne!(DEFAULT_LOG_RATE, 0);
         let _writelock = get_env_lock().write();
+        let _sinklock = get_sink_lock().read();
         static mut COUNTER: Counter = create_counter!("test_lograte_env", 0);
         env::set_var("SOLANA_DEFAULT_LOG_RATE", "50");
         inc_counter!(COUNTER, Level::Info, 2);
@@ -198,4 +773,63 @@ mod tests {
             assert_eq!(COUNTER2.lograte.load(Ordering::Relaxed), DEFAULT_LOG_RATE);
         }
     }
+
+    #[test]
+    fn test_flush_interval_env() {
+        assert_ne!(DEFAULT_FLUSH_INTERVAL_MS, 0);
+        let _writelock = get_env_lock().write();
+        let _sinklock = get_sink_lock().read();
+        static mut COUNTER: Counter = create_counter!("test_flush_interval_env", 1000);
+        env::set_var("SOLANA_METRICS_FLUSH_INTERVAL", "500");
+        unsafe {
+            COUNTER.init();
+        }
+        inc_counter!(COUNTER, Level::Info, 1);
+        unsafe {
+            assert_eq!(COUNTER.flush_interval.load(Ordering::Relaxed), 500);
+        }
+
+        static mut COUNTER2: Counter = create_counter!("test_flush_interval_env", 1000);
+        env::set_var("SOLANA_METRICS_FLUSH_INTERVAL", "0");
+        unsafe {
+            COUNTER2.init();
+        }
+        inc_counter!(COUNTER2, Level::Info, 1);
+        unsafe {
+            assert_eq!(
+                COUNTER2.flush_interval.load(Ordering::Relaxed),
+                DEFAULT_FLUSH_INTERVAL_MS
+            );
+        }
+    }
+
+    #[test]
+    fn test_flush_interval_bounds_independent_of_count() {
+        // this test swaps the default sink *and* sets SOLANA_METRICS_FLUSH_INTERVAL,
+        // so it needs to serialize against both kinds of tests
+        let _envlock = get_env_lock().write();
+        let _sinklock = get_sink_lock().write();
+        let sink = Arc::new(CapturingSink::default());
+        set_default_sink(sink.clone());
+
+        // a lograte this high means the count-based trigger never fires across
+        // the handful of calls below; only the time-based trigger can flush
+        static mut COUNTER: Counter =
+            create_counter!("test_flush_interval_bounds_independent_of_count", 1_000_000);
+        env::set_var("SOLANA_METRICS_FLUSH_INTERVAL", "1");
+        unsafe {
+            COUNTER.init();
+        }
+        inc_counter!(COUNTER, Level::Info, 1);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        inc_counter!(COUNTER, Level::Info, 1);
+
+        let reports = sink.reports.lock().unwrap();
+        assert_eq!(reports.len(), 1);
+
+        set_default_sink(Arc::new(crate::counter::InfluxDbSink));
+        // "0" resolves to DEFAULT_FLUSH_INTERVAL_MS, so counters in later tests
+        // don't inherit the 1ms interval
+        env::set_var("SOLANA_METRICS_FLUSH_INTERVAL", "0");
+    }
 }